@@ -1,6 +1,15 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use quote::ToTokens;
-use std::{env, fs, path::Path};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{
+	collections::HashSet,
+	env,
+	fmt::Write as _,
+	fs,
+	path::{Path, PathBuf},
+	sync::Mutex,
+};
 use syn::{
 	Attribute, File, ImplItem, Item, ItemEnum, ItemFn, ItemImpl, ItemStruct, ItemTrait, TraitItem,
 	Visibility,
@@ -12,33 +21,600 @@ struct TestStruct {
 	j: Box<TestStruct>,
 }
 
+/// 1 ファイル分のサマリー（`--format json` 用）
+#[derive(Serialize)]
+struct FileSummary {
+	path: String,
+	items: Vec<ItemSummary>,
+}
+
+/// アイテム1つ分のサマリー（`mod { ... }` の中のアイテムも含む。`--format json` 用）
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+enum ItemSummary {
+	Fn(FnSummary),
+	Struct(StructSummary),
+	Enum(EnumSummary),
+	Trait(TraitSummary),
+	Impl(ImplSummary),
+	Mod(ModSummary),
+	Use(UseSummary),
+}
+
+/// `--public` で再エクスポートとして扱う `pub use` / `pub(crate) use` 文。
+#[derive(Serialize)]
+struct UseSummary {
+	visibility: String,
+	path: String,
+	doc: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ModSummary {
+	visibility: String,
+	ident: String,
+	doc: Vec<String>,
+	items: Vec<ItemSummary>,
+}
+
+#[derive(Serialize)]
+struct FnSummary {
+	visibility: String,
+	ident: String,
+	generics: String,
+	signature: String,
+	doc: Vec<String>,
+	derive: Vec<String>,
+	cfg: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct StructSummary {
+	visibility: String,
+	ident: String,
+	generics: String,
+	fields: String,
+	doc: Vec<String>,
+	derive: Vec<String>,
+	cfg: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct EnumSummary {
+	visibility: String,
+	ident: String,
+	generics: String,
+	doc: Vec<String>,
+	derive: Vec<String>,
+	cfg: Vec<String>,
+	variants: Vec<VariantSummary>,
+}
+
+#[derive(Serialize)]
+struct VariantSummary {
+	signature: String,
+	doc: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct TraitSummary {
+	visibility: String,
+	ident: String,
+	generics: String,
+	doc: Vec<String>,
+	methods: Vec<FnSummary>,
+}
+
+#[derive(Serialize)]
+struct ImplSummary {
+	header: String,
+	doc: Vec<String>,
+	methods: Vec<FnSummary>,
+}
+
+/// ワーカースレッドが 1 ファイル分の処理で作る出力。
+/// `--format json` かどうかで中身が変わるだけで、どちらもドライバ側で
+/// パス順に並べ直してから出力する。
+enum FileOutput {
+	Text(String),
+	Json(FileSummary),
+}
+
+/// 発見済みシンボルの共有キャッシュ。全ワーカーから書き込まれるので
+/// `Mutex` で保護する。`--index` で木全体のシンボル索引を出すときの元データになる。
+#[derive(Default)]
+struct SymbolCache {
+	records: Mutex<Vec<SymbolRecord>>,
+}
+
+struct SymbolRecord {
+	ident: String,
+	kind: &'static str,
+	visibility: String,
+	file: String,
+	module_path: String,
+}
+
+impl SymbolCache {
+	fn record(
+		&self,
+		ident: String,
+		kind: &'static str,
+		visibility: String,
+		file: String,
+		module_path: String,
+	) {
+		self.records.lock().unwrap().push(SymbolRecord {
+			ident,
+			kind,
+			visibility,
+			file,
+			module_path,
+		});
+	}
+
+	/// 索引表示用に `ident` → (`file`, `module_path`) 順でソートした一覧を返す。
+	fn sorted_entries(&self) -> Vec<SymbolIndexEntry> {
+		let mut entries: Vec<SymbolIndexEntry> = self
+			.records
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|r| SymbolIndexEntry {
+				ident: r.ident.clone(),
+				kind: r.kind,
+				visibility: r.visibility.clone(),
+				file: r.file.clone(),
+				module_path: r.module_path.clone(),
+			})
+			.collect();
+		entries.sort_by(|a, b| (&a.ident, &a.file, &a.module_path).cmp(&(&b.ident, &b.file, &b.module_path)));
+		entries
+	}
+}
+
+/// `--index` が出す平坦なシンボル索引の1行分。
+#[derive(Serialize)]
+struct SymbolIndexEntry {
+	ident: String,
+	kind: &'static str,
+	visibility: String,
+	file: String,
+	module_path: String,
+}
+
+/// `--format json --index` のときの出力全体。
+#[derive(Serialize)]
+struct IndexedOutput {
+	files: Vec<FileSummary>,
+	index: Vec<SymbolIndexEntry>,
+}
+
+/// トップレベル（と `mod { ... }` の中）のシンボルをキャッシュに登録する。
+/// `module_path` は今たどっている `mod` のネストを `::` で連結したもの（トップレベルは空文字）。
+/// `public_only` を立てると、`print_item`/`build_item_summary` と同じ基準で
+/// 非公開のシンボルと、非公開な `mod` の中身をまるごと索引から除く。
+fn collect_symbols(cache: &SymbolCache, items: &[Item], file: &str, module_path: &str, public_only: bool) {
+	for item in items {
+		if public_only {
+			if let Some(vis) = item_vis(item) {
+				if !is_pub_enough(vis) {
+					continue;
+				}
+			}
+		}
+
+		match item {
+			Item::Fn(f) => cache.record(
+				f.sig.ident.to_string(),
+				"fn",
+				print_vis(&f.vis),
+				file.to_string(),
+				module_path.to_string(),
+			),
+			Item::Struct(s) => cache.record(
+				s.ident.to_string(),
+				"struct",
+				print_vis(&s.vis),
+				file.to_string(),
+				module_path.to_string(),
+			),
+			Item::Enum(e) => cache.record(
+				e.ident.to_string(),
+				"enum",
+				print_vis(&e.vis),
+				file.to_string(),
+				module_path.to_string(),
+			),
+			Item::Trait(t) => cache.record(
+				t.ident.to_string(),
+				"trait",
+				print_vis(&t.vis),
+				file.to_string(),
+				module_path.to_string(),
+			),
+			Item::Mod(m) => {
+				if let Some((_, inner)) = &m.content {
+					let nested_path = if module_path.is_empty() {
+						m.ident.to_string()
+					} else {
+						format!("{module_path}::{}", m.ident)
+					};
+					collect_symbols(cache, inner, file, &nested_path, public_only);
+				}
+			}
+			_other => {}
+		}
+	}
+}
+
 fn main() -> Result<()> {
-	let root = env::args().nth(1).unwrap_or_else(|| ".".to_string());
-	let root = Path::new(&root);
+	let args: Vec<String> = env::args().skip(1).collect();
+	let mut root_arg = None;
+	let mut format_json = false;
+	let mut index = false;
+	let mut public_only = false;
+	let mut i = 0;
+	while i < args.len() {
+		match args[i].as_str() {
+			"--format" => match args.get(i + 1).map(String::as_str) {
+				Some("json") => {
+					format_json = true;
+					i += 2;
+				}
+				Some(other) => bail!("Unknown --format value: {other} (expected \"json\")"),
+				None => bail!("--format requires a value (expected \"json\")"),
+			},
+			"--index" => {
+				index = true;
+				i += 1;
+			}
+			// NOTE: 各ファイルはそれぞれ独立に処理されるので、`mod foo;` で別ファイルへ
+			// 分岐した先が実際にクレート外へ公開されているか（= 辿っていく `mod` が
+			// すべて `pub` か）はここでは判定できない。`pub fn` が非公開の `mod foo;`
+			// からしか参照されていなくても、このファイル単体では公開として表示される。
+			"--public" => {
+				public_only = true;
+				i += 1;
+			}
+			other => {
+				if root_arg.is_none() {
+					root_arg = Some(other.to_string());
+				}
+				i += 1;
+			}
+		}
+	}
+	let root = root_arg.unwrap_or_else(|| ".".to_string());
+	let output = run(Path::new(&root), format_json, index, public_only)?;
+	print!("{output}");
 
-	for entry in WalkDir::new(root)
+	Ok(())
+}
+
+/// CLI 引数を解決したあとの本体処理。`main` から出力の組み立てまでを切り出してあるので、
+/// テストからは `print!`/`println!` を介さず戻り値の文字列を直接検証できる。
+fn run(root: &Path, format_json: bool, index: bool, public_only: bool) -> Result<String> {
+	let paths: Vec<PathBuf> = WalkDir::new(root)
 		.into_iter()
 		.filter_map(|e| e.ok())
 		.filter(|e| e.path().extension().map(|e| e == "rs").unwrap_or(false))
-	{
-		let path = entry.path();
-		let src = fs::read_to_string(path)
-			.with_context(|| format!("Failed to read {}", path.display()))?;
-		let file: File =
-			syn::parse_file(&src).with_context(|| format!("Failed to parse {}", path.display()))?;
+		.map(|e| e.path().to_path_buf())
+		.collect();
+
+	let symbol_cache = SymbolCache::default();
+
+	let mut results: Vec<(String, FileOutput)> = paths
+		.par_iter()
+		.map(|path| -> Result<(String, FileOutput)> {
+			let src = fs::read_to_string(path)
+				.with_context(|| format!("Failed to read {}", path.display()))?;
+			let file: File = syn::parse_file(&src)
+				.with_context(|| format!("Failed to parse {}", path.display()))?;
+			let rel_path = path.strip_prefix(root).unwrap_or(path).display().to_string();
+
+			collect_symbols(&symbol_cache, &file.items, &rel_path, "", public_only);
+			let non_public_types = collect_non_public_type_idents(&file.items);
+
+			let output = if format_json {
+				let items = file
+					.items
+					.iter()
+					.filter_map(|item| build_item_summary(item, public_only, &non_public_types))
+					.collect();
+				FileOutput::Json(FileSummary {
+					path: rel_path.clone(),
+					items,
+				})
+			} else {
+				let mut buf = String::new();
+				let _ = writeln!(buf, "// ************* {rel_path}");
+				for item in &file.items {
+					print_item(&mut buf, item, 0, public_only, &non_public_types);
+				}
+				FileOutput::Text(buf)
+			};
 
-		// ファイル見出し
-		println!(
-			"// ************* {}",
-			path.strip_prefix(root).unwrap_or(path).display()
-		);
+			Ok((rel_path, output))
+		})
+		.collect::<Result<Vec<_>>>()?;
 
-		for item in file.items {
-			print_item(&item, 0);
+	// スレッドの完了順はファイル列挙順と一致しないので、出力はパスでソートして揃える
+	results.sort_by(|a, b| a.0.cmp(&b.0));
+
+	let mut out = String::new();
+
+	if format_json {
+		let summaries: Vec<FileSummary> = results
+			.into_iter()
+			.map(|(_, output)| match output {
+				FileOutput::Json(summary) => summary,
+				FileOutput::Text(_) => unreachable!("format_json は常に FileOutput::Json を生成する"),
+			})
+			.collect();
+		if index {
+			let indexed = IndexedOutput {
+				files: summaries,
+				index: symbol_cache.sorted_entries(),
+			};
+			let _ = writeln!(out, "{}", serde_json::to_string_pretty(&indexed)?);
+		} else {
+			let _ = writeln!(out, "{}", serde_json::to_string_pretty(&summaries)?);
+		}
+	} else {
+		for (_, output) in results {
+			match output {
+				FileOutput::Text(buf) => out.push_str(&buf),
+				FileOutput::Json(_) => unreachable!("!format_json は常に FileOutput::Text を生成する"),
+			}
+		}
+		if index {
+			let _ = writeln!(out, "// ************* INDEX");
+			for entry in symbol_cache.sorted_entries() {
+				let module = if entry.module_path.is_empty() {
+					"-".to_string()
+				} else {
+					entry.module_path
+				};
+				let _ = writeln!(
+					out,
+					"{} {}{} {} {}",
+					entry.kind, entry.visibility, entry.ident, entry.file, module
+				);
+			}
 		}
 	}
 
-	Ok(())
+	Ok(out)
+}
+
+fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("doc"))
+		.filter_map(|attr| match &attr.meta {
+			syn::Meta::NameValue(nv) => match &nv.value {
+				syn::Expr::Lit(expr_lit) => match &expr_lit.lit {
+					syn::Lit::Str(doc) => Some(doc.value()),
+					_ => None,
+				},
+				_ => None,
+			},
+			_ => None,
+		})
+		.collect()
+}
+
+/// `#[derive(...)]` に書かれたトレイト名の一覧を取り出す。
+fn derive_list(attrs: &[Attribute]) -> Vec<String> {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("derive"))
+		.filter_map(|attr| {
+			attr.parse_args_with(syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+				.ok()
+		})
+		.flat_map(|paths| paths.into_iter().map(|p| p.to_token_stream().to_string()))
+		.collect()
+}
+
+/// `#[cfg(...)]` の条件式を取り出す（複数あれば複数件）。
+fn cfg_list(attrs: &[Attribute]) -> Vec<String> {
+	attrs
+		.iter()
+		.filter(|attr| attr.path().is_ident("cfg"))
+		.filter_map(|attr| match &attr.meta {
+			syn::Meta::List(list) => Some(list.tokens.to_token_stream().to_string()),
+			_ => None,
+		})
+		.collect()
+}
+
+/// `derive`/`cfg` を1行にまとめたコメント（どちらも無ければ `None`）。
+fn attr_summary_line(attrs: &[Attribute]) -> Option<String> {
+	let derives = derive_list(attrs);
+	let cfgs = cfg_list(attrs);
+	if derives.is_empty() && cfgs.is_empty() {
+		return None;
+	}
+
+	let mut parts = Vec::new();
+	if !derives.is_empty() {
+		parts.push(format!("derive({})", derives.join(", ")));
+	}
+	for cfg in &cfgs {
+		parts.push(format!("cfg({cfg})"));
+	}
+	Some(parts.join(" "))
+}
+
+/// アイテム（トップレベルと `mod { ... }` の中）を `ItemSummary` に変換する（`--format json` 用）。
+/// `print_item` と同じ対象（Fn/Struct/Enum/Trait/Impl/Mod、`--public` 時は Use も）を扱う。
+/// `non_public_types` は同一ファイル内の非公開な型・トレイト名の集合（`impl` の絞り込みに使う）。
+fn build_item_summary(item: &Item, public_only: bool, non_public_types: &HashSet<String>) -> Option<ItemSummary> {
+	// `use` は `pub(crate)` も再エクスポートとして数えるので、他アイテムより緩い
+	// `is_reexport_visible` の方で別途判定する（下の `Item::Use` 節を参照）。
+	if public_only && !matches!(item, Item::Use(_)) {
+		if let Some(vis) = item_vis(item) {
+			if !is_pub_enough(vis) {
+				return None;
+			}
+		}
+	}
+
+	match item {
+		Item::Fn(f) => Some(ItemSummary::Fn(build_fn_summary(f))),
+		Item::Struct(s) => Some(ItemSummary::Struct(build_struct_summary(s))),
+		Item::Enum(e) => Some(ItemSummary::Enum(build_enum_summary(e))),
+		Item::Trait(t) => Some(ItemSummary::Trait(build_trait_summary(t))),
+		Item::Impl(i) => build_impl_summary(i, public_only, non_public_types).map(ItemSummary::Impl),
+		Item::Mod(m) => Some(ItemSummary::Mod(build_mod_summary(m, public_only, non_public_types))),
+		Item::Use(u) => (public_only && is_reexport_visible(&u.vis)).then(|| ItemSummary::Use(build_use_summary(u))),
+		_other => None,
+	}
+}
+
+fn build_mod_summary(m: &syn::ItemMod, public_only: bool, non_public_types: &HashSet<String>) -> ModSummary {
+	ModSummary {
+		visibility: print_vis(&m.vis),
+		ident: m.ident.to_string(),
+		doc: doc_lines(&m.attrs),
+		items: m
+			.content
+			.as_ref()
+			.map(|(_, items)| {
+				items
+					.iter()
+					.filter_map(|item| build_item_summary(item, public_only, non_public_types))
+					.collect()
+			})
+			.unwrap_or_default(),
+	}
+}
+
+fn build_use_summary(u: &syn::ItemUse) -> UseSummary {
+	UseSummary {
+		visibility: print_vis(&u.vis),
+		path: u.tree.to_token_stream().to_string(),
+		doc: doc_lines(&u.attrs),
+	}
+}
+
+fn build_fn_summary(f: &ItemFn) -> FnSummary {
+	FnSummary {
+		visibility: print_vis(&f.vis),
+		ident: f.sig.ident.to_string(),
+		generics: f.sig.generics.to_token_stream().to_string(),
+		signature: f.sig.to_token_stream().to_string(),
+		doc: doc_lines(&f.attrs),
+		derive: derive_list(&f.attrs),
+		cfg: cfg_list(&f.attrs),
+	}
+}
+
+fn build_struct_summary(s: &ItemStruct) -> StructSummary {
+	StructSummary {
+		visibility: print_vis(&s.vis),
+		ident: s.ident.to_string(),
+		generics: s.generics.to_token_stream().to_string(),
+		fields: s.fields.to_token_stream().to_string(),
+		doc: doc_lines(&s.attrs),
+		derive: derive_list(&s.attrs),
+		cfg: cfg_list(&s.attrs),
+	}
+}
+
+fn build_enum_summary(e: &ItemEnum) -> EnumSummary {
+	EnumSummary {
+		visibility: print_vis(&e.vis),
+		ident: e.ident.to_string(),
+		generics: e.generics.to_token_stream().to_string(),
+		doc: doc_lines(&e.attrs),
+		derive: derive_list(&e.attrs),
+		cfg: cfg_list(&e.attrs),
+		variants: e
+			.variants
+			.iter()
+			.map(|v| VariantSummary {
+				signature: v.to_token_stream().to_string(),
+				doc: doc_lines(&v.attrs),
+			})
+			.collect(),
+	}
+}
+
+fn build_trait_summary(t: &ItemTrait) -> TraitSummary {
+	TraitSummary {
+		visibility: print_vis(&t.vis),
+		ident: t.ident.to_string(),
+		generics: t.generics.to_token_stream().to_string(),
+		doc: doc_lines(&t.attrs),
+		methods: t
+			.items
+			.iter()
+			.filter_map(|item| match item {
+				TraitItem::Fn(m) => Some(FnSummary {
+					visibility: String::new(),
+					ident: m.sig.ident.to_string(),
+					generics: m.sig.generics.to_token_stream().to_string(),
+					signature: m.sig.to_token_stream().to_string(),
+					doc: doc_lines(&m.attrs),
+					derive: derive_list(&m.attrs),
+					cfg: cfg_list(&m.attrs),
+				}),
+				_ => None,
+			})
+			.collect(),
+	}
+}
+
+fn build_impl_summary(i: &ItemImpl, public_only: bool, non_public_types: &HashSet<String>) -> Option<ImplSummary> {
+	if public_only && impl_targets_non_public_type(i, non_public_types) {
+		return None;
+	}
+
+	let header = if let Some((bang, path, for_token)) = &i.trait_ {
+		format!(
+			"impl{} {} {} {} {}",
+			i.generics.to_token_stream(),
+			bang.to_token_stream(),
+			path.to_token_stream(),
+			for_token.to_token_stream(),
+			i.self_ty.to_token_stream()
+		)
+	} else {
+		format!("impl{} {}", i.generics.to_token_stream(), i.self_ty.to_token_stream())
+	};
+
+	// トレイト実装のメソッドはトレイト自体が公開かどうかで決まるので、絞り込むのは
+	// inherent impl (`impl Type`) の `pub` メソッドだけ
+	let is_inherent = i.trait_.is_none();
+
+	Some(ImplSummary {
+		header,
+		doc: doc_lines(&i.attrs),
+		methods: i
+			.items
+			.iter()
+			.filter_map(|item| match item {
+				ImplItem::Fn(m) => {
+					if public_only && is_inherent && !is_pub_enough(&m.vis) {
+						return None;
+					}
+					Some(FnSummary {
+						visibility: print_vis(&m.vis),
+						ident: m.sig.ident.to_string(),
+						generics: m.sig.generics.to_token_stream().to_string(),
+						signature: m.sig.to_token_stream().to_string(),
+						doc: doc_lines(&m.attrs),
+						derive: derive_list(&m.attrs),
+						cfg: cfg_list(&m.attrs),
+					})
+				}
+				_ => None,
+			})
+			.collect(),
+	})
 }
 
 fn indent(level: usize) -> String {
@@ -46,20 +622,18 @@ fn indent(level: usize) -> String {
 }
 
 // /// doc コメントだけを再生（syn v2 の API に対応）
-fn print_doc_attrs(attrs: &[Attribute], indent_level: usize) {
+fn print_doc_attrs(out: &mut String, attrs: &[Attribute], indent_level: usize) {
 	let ind = indent(indent_level);
-	for attr in attrs {
-		if !attr.path().is_ident("doc") {
-			continue;
-		}
-		// #[doc = "..."] をパース
-		if let syn::Meta::NameValue(nv) = &attr.meta {
-			if let syn::Expr::Lit(expr_lit) = &nv.value {
-				if let syn::Lit::Str(doc) = &expr_lit.lit {
-					println!("{ind}/// {}", doc.value());
-				}
-			}
-		}
+	for doc in doc_lines(attrs) {
+		let _ = writeln!(out, "{ind}/// {doc}");
+	}
+}
+
+/// `derive`/`cfg` の要約コメントを1行書く（無ければ何もしない）。
+fn print_attr_summary(out: &mut String, attrs: &[Attribute], indent_level: usize) {
+	if let Some(line) = attr_summary_line(attrs) {
+		let ind = indent(indent_level);
+		let _ = writeln!(out, "{ind}// {line}");
 	}
 }
 
@@ -74,30 +648,156 @@ fn print_vis(vis: &Visibility) -> String {
 	}
 }
 
-fn print_item(item: &Item, indent_level: usize) {
+/// `--public` 判定用: クレート外から見えるか（`pub`）を判定する。
+/// `pub(crate)` はクレート内では共有されてもクレート外からは見えないので、
+/// `pub(super)` / `pub(in ...)` / 非公開と同様に公開 API には含めない。
+fn is_pub_enough(vis: &Visibility) -> bool {
+	matches!(vis, Visibility::Public(_))
+}
+
+/// `use` 文を再エクスポートとして扱ってよいか判定する。`pub` だけでなく `pub(crate) use` も
+/// クレート内の他モジュールから見える道筋を提供するので再エクスポートとして数える。
+/// `pub(super)` / `pub(in ...)` / 非公開の `use` は単なる自分用の取り込みなので対象外。
+fn is_reexport_visible(vis: &Visibility) -> bool {
+	match vis {
+		Visibility::Public(_) => true,
+		Visibility::Restricted(r) => r.path.is_ident("crate"),
+		Visibility::Inherited => false,
+	}
+}
+
+/// アイテム自身が宣言している可視性。`impl` ブロックのように可視性を持たない種類は `None`。
+fn item_vis(item: &Item) -> Option<&Visibility> {
 	match item {
-		Item::Fn(f) => print_item_fn(f, indent_level),
-		Item::Struct(s) => print_item_struct(s, indent_level),
-		Item::Enum(e) => print_item_enum(e, indent_level),
-		Item::Trait(t) => print_item_trait(t, indent_level),
-		Item::Impl(i) => print_item_impl(i, indent_level),
-		// 必要ならここに Type / Const / Mod などを追加
+		Item::Fn(f) => Some(&f.vis),
+		Item::Struct(s) => Some(&s.vis),
+		Item::Enum(e) => Some(&e.vis),
+		Item::Trait(t) => Some(&t.vis),
+		Item::Mod(m) => Some(&m.vis),
+		Item::Use(u) => Some(&u.vis),
+		_ => None,
+	}
+}
+
+/// このファイル内でトップレベル（と `mod { ... }` の中）に宣言されている型・トレイトのうち、
+/// `--public` の基準で非公開なものの識別子を集める。`impl` 側は自身の可視性を持たないので、
+/// 対象の型・トレイトが同一ファイル内で非公開と分かっていれば impl ブロックごと隠すのに使う。
+/// 他ファイルで宣言された型は追えないので、その場合は従来通り表示したままになる
+/// （`--public` の既知の制約。引数解析の NOTE を参照）。
+fn collect_non_public_type_idents(items: &[Item]) -> HashSet<String> {
+	let mut idents = HashSet::new();
+	for item in items {
+		match item {
+			Item::Struct(s) if !is_pub_enough(&s.vis) => {
+				idents.insert(s.ident.to_string());
+			}
+			Item::Enum(e) if !is_pub_enough(&e.vis) => {
+				idents.insert(e.ident.to_string());
+			}
+			Item::Trait(t) if !is_pub_enough(&t.vis) => {
+				idents.insert(t.ident.to_string());
+			}
+			Item::Mod(m) => {
+				if let Some((_, inner)) = &m.content {
+					idents.extend(collect_non_public_type_idents(inner));
+				}
+			}
+			_other => {}
+		}
+	}
+	idents
+}
+
+/// `impl` の型の末尾セグメント名だけを取り出す（ジェネリクスや参照越しの型は対象外）。
+fn simple_type_ident(ty: &syn::Type) -> Option<String> {
+	match ty {
+		syn::Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()),
+		_ => None,
+	}
+}
+
+/// `--public` のとき、実装対象の型またはトレイトが同一ファイル内で非公開と分かっている
+/// `impl` ブロックかどうかを判定する。
+fn impl_targets_non_public_type(i: &ItemImpl, non_public_types: &HashSet<String>) -> bool {
+	let self_ty_hidden = simple_type_ident(&i.self_ty)
+		.map(|ident| non_public_types.contains(&ident))
+		.unwrap_or(false);
+	let trait_hidden = i
+		.trait_
+		.as_ref()
+		.and_then(|(_, path, _)| path.segments.last())
+		.map(|seg| non_public_types.contains(&seg.ident.to_string()))
+		.unwrap_or(false);
+	self_ty_hidden || trait_hidden
+}
+
+fn print_item(out: &mut String, item: &Item, indent_level: usize, public_only: bool, non_public_types: &HashSet<String>) {
+	// `use` は `pub(crate)` も再エクスポートとして数えるので、他アイテムより緩い
+	// `is_reexport_visible` の方で別途判定する（下の `Item::Use` 節を参照）。
+	if public_only && !matches!(item, Item::Use(_)) {
+		if let Some(vis) = item_vis(item) {
+			if !is_pub_enough(vis) {
+				return;
+			}
+		}
+	}
+
+	match item {
+		Item::Fn(f) => print_item_fn(out, f, indent_level),
+		Item::Struct(s) => print_item_struct(out, s, indent_level),
+		Item::Enum(e) => print_item_enum(out, e, indent_level),
+		Item::Trait(t) => print_item_trait(out, t, indent_level),
+		Item::Impl(i) => print_item_impl(out, i, indent_level, public_only, non_public_types),
+		Item::Mod(m) => print_item_mod(out, m, indent_level, public_only, non_public_types),
+		// `--public` のときだけ、再エクスポートとして公開 API に数える（`pub(crate) use` も含む）
+		Item::Use(u) if public_only && is_reexport_visible(&u.vis) => print_item_use(out, u, indent_level),
+		// 必要ならここに Type / Const などを追加
 		_other => {
-			//println!("{ind}// [skipped]", ind = indent(indent_level));
+			//writeln!(out, "{ind}// [skipped]", ind = indent(indent_level));
 		}
 	}
 }
 
-fn print_item_fn(f: &ItemFn, indent_level: usize) {
+fn print_item_mod(out: &mut String, m: &syn::ItemMod, indent_level: usize, public_only: bool, non_public_types: &HashSet<String>) {
 	let ind = indent(indent_level);
-	print_doc_attrs(&f.attrs, indent_level);
+	print_doc_attrs(out, &m.attrs, indent_level);
+
+	let vis = print_vis(&m.vis);
+	let ident = &m.ident;
+
+	match &m.content {
+		Some((_, items)) => {
+			let _ = writeln!(out, "{ind}{}mod {} {{", vis, ident);
+			for item in items {
+				print_item(out, item, indent_level + 1, public_only, non_public_types);
+			}
+			let _ = writeln!(out, "{ind}}}");
+		}
+		None => {
+			// `mod foo;` は別ファイルを参照するだけなので中身はここでは分からない
+			let _ = writeln!(out, "{ind}{}mod {};", vis, ident);
+		}
+	}
+}
+
+fn print_item_use(out: &mut String, u: &syn::ItemUse, indent_level: usize) {
+	let ind = indent(indent_level);
+	print_doc_attrs(out, &u.attrs, indent_level);
+	let _ = writeln!(out, "{ind}{}use {};", print_vis(&u.vis), u.tree.to_token_stream());
+}
+
+fn print_item_fn(out: &mut String, f: &ItemFn, indent_level: usize) {
+	let ind = indent(indent_level);
+	print_doc_attrs(out, &f.attrs, indent_level);
+	print_attr_summary(out, &f.attrs, indent_level);
 	let sig = f.sig.to_token_stream().to_string();
-	println!("{ind}{}{};", print_vis(&f.vis), sig);
+	let _ = writeln!(out, "{ind}{}{};", print_vis(&f.vis), sig);
 }
 
-fn print_item_struct(s: &ItemStruct, indent_level: usize) {
+fn print_item_struct(out: &mut String, s: &ItemStruct, indent_level: usize) {
 	let ind = indent(indent_level);
-	print_doc_attrs(&s.attrs, indent_level);
+	print_doc_attrs(out, &s.attrs, indent_level);
+	print_attr_summary(out, &s.attrs, indent_level);
 
 	let vis = print_vis(&s.vis);
 	let ident = &s.ident;
@@ -107,7 +807,8 @@ fn print_item_struct(s: &ItemStruct, indent_level: usize) {
 
 	match fields {
 		syn::Fields::Named(_) | syn::Fields::Unnamed(_) => {
-			println!(
+			let _ = writeln!(
+				out,
 				"{ind}{}struct {}{} {}{}",
 				vis,
 				ident,
@@ -119,7 +820,8 @@ fn print_item_struct(s: &ItemStruct, indent_level: usize) {
 			);
 		}
 		syn::Fields::Unit => {
-			println!(
+			let _ = writeln!(
+				out,
 				"{ind}{}struct {}{};{}",
 				vis,
 				ident,
@@ -132,16 +834,18 @@ fn print_item_struct(s: &ItemStruct, indent_level: usize) {
 	}
 }
 
-fn print_item_enum(e: &ItemEnum, indent_level: usize) {
+fn print_item_enum(out: &mut String, e: &ItemEnum, indent_level: usize) {
 	let ind = indent(indent_level);
-	print_doc_attrs(&e.attrs, indent_level);
+	print_doc_attrs(out, &e.attrs, indent_level);
+	print_attr_summary(out, &e.attrs, indent_level);
 
 	let vis = print_vis(&e.vis);
 	let ident = &e.ident;
 	let generics = &e.generics;
 	let where_clause = e.generics.where_clause.as_ref();
 
-	println!(
+	let _ = writeln!(
+		out,
 		"{ind}{}enum {}{}{} {{",
 		vis,
 		ident,
@@ -152,17 +856,17 @@ fn print_item_enum(e: &ItemEnum, indent_level: usize) {
 	);
 
 	for v in &e.variants {
-		print_doc_attrs(&v.attrs, indent_level + 1);
+		print_doc_attrs(out, &v.attrs, indent_level + 1);
 		let vind = indent(indent_level + 1);
-		println!("{vind}{}", v.to_token_stream());
+		let _ = writeln!(out, "{vind}{}", v.to_token_stream());
 	}
 
-	println!("{ind}}}");
+	let _ = writeln!(out, "{ind}}}");
 }
 
-fn print_item_trait(t: &ItemTrait, indent_level: usize) {
+fn print_item_trait(out: &mut String, t: &ItemTrait, indent_level: usize) {
 	let ind = indent(indent_level);
-	print_doc_attrs(&t.attrs, indent_level);
+	print_doc_attrs(out, &t.attrs, indent_level);
 
 	let vis = print_vis(&t.vis);
 	let ident = &t.ident;
@@ -170,33 +874,38 @@ fn print_item_trait(t: &ItemTrait, indent_level: usize) {
 	let supertraits = &t.supertraits;
 	let where_clause = t.generics.where_clause.as_ref();
 
-	print!("{ind}{}trait {}{}", vis, ident, generics.to_token_stream());
+	let _ = write!(out, "{ind}{}trait {}{}", vis, ident, generics.to_token_stream());
 	if !supertraits.is_empty() {
-		print!(": {}", supertraits.to_token_stream());
+		let _ = write!(out, ": {}", supertraits.to_token_stream());
 	}
 	if let Some(w) = where_clause {
-		print!(" {}", w.to_token_stream());
+		let _ = write!(out, " {}", w.to_token_stream());
 	}
-	println!(" {{");
+	let _ = writeln!(out, " {{");
 
 	for item in &t.items {
-		match item {
-			TraitItem::Fn(m) => {
-				print_doc_attrs(&m.attrs, indent_level + 1);
-				let mind = indent(indent_level + 1);
-				let sig = m.sig.to_token_stream().to_string();
-				println!("{mind}fn {};", sig.trim_start_matches("fn "));
-			}
-			_ => {}
+		if let TraitItem::Fn(m) = item {
+			print_doc_attrs(out, &m.attrs, indent_level + 1);
+			print_attr_summary(out, &m.attrs, indent_level + 1);
+			let mind = indent(indent_level + 1);
+			let sig = m.sig.to_token_stream().to_string();
+			let _ = writeln!(out, "{mind}fn {};", sig.trim_start_matches("fn "));
 		}
 	}
 
-	println!("{ind}}}");
+	let _ = writeln!(out, "{ind}}}");
 }
 
-fn print_item_impl(i: &ItemImpl, indent_level: usize) {
+fn print_item_impl(out: &mut String, i: &ItemImpl, indent_level: usize, public_only: bool, non_public_types: &HashSet<String>) {
+	if public_only && impl_targets_non_public_type(i, non_public_types) {
+		return;
+	}
+
 	let ind = indent(indent_level);
-	print_doc_attrs(&i.attrs, indent_level);
+	print_doc_attrs(out, &i.attrs, indent_level);
+	// トレイト実装のメソッドはトレイト自体が公開かどうかで決まり、自分の可視性を
+	// 持たないので、ここで絞り込むのは inherent impl (`impl Type`) の `pub` メソッドだけ
+	let is_inherent = i.trait_.is_none();
 
 	let unsafety = i
 		.unsafety
@@ -227,21 +936,194 @@ fn print_item_impl(i: &ItemImpl, indent_level: usize) {
 
 	let where_clause = i.generics.where_clause.as_ref();
 	if let Some(w) = where_clause {
-		println!("{ind}{header} {} {{", w.to_token_stream());
+		let _ = writeln!(out, "{ind}{header} {} {{", w.to_token_stream());
 	} else {
-		println!("{ind}{header} {{");
+		let _ = writeln!(out, "{ind}{header} {{");
 	}
 
 	for item in &i.items {
-		match item {
-			ImplItem::Fn(m) => {
-				print_doc_attrs(&m.attrs, indent_level + 1);
-				let mind = indent(indent_level + 1);
-				let sig = m.sig.to_token_stream().to_string();
-				println!("{mind}fn {};", sig.trim_start_matches("fn "));
+		if let ImplItem::Fn(m) = item {
+			if public_only && is_inherent && !is_pub_enough(&m.vis) {
+				continue;
 			}
-			_ => {}
+			print_doc_attrs(out, &m.attrs, indent_level + 1);
+			print_attr_summary(out, &m.attrs, indent_level + 1);
+			let mind = indent(indent_level + 1);
+			let sig = m.sig.to_token_stream().to_string();
+			let _ = writeln!(out, "{mind}fn {};", sig.trim_start_matches("fn "));
 		}
 	}
-	println!("{ind}}}");
+	let _ = writeln!(out, "{ind}}}");
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// テストごとに使い捨てのディレクトリを用意する（プロセスIDで衝突を避ける）。
+	fn fixture_dir(name: &str) -> PathBuf {
+		let dir = env::temp_dir().join(format!("summary4ai-test-{name}-{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+		fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	fn write_multi_file_fixture(dir: &Path) {
+		fs::write(
+			dir.join("a.rs"),
+			"pub fn pub_fn() {}\nfn private_fn() {}\npub(crate) struct Internal;\n",
+		)
+		.unwrap();
+		fs::write(
+			dir.join("b.rs"),
+			"pub mod inner {\n    pub fn inner_pub() {}\n    fn inner_private() {}\n}\n",
+		)
+		.unwrap();
+		fs::write(dir.join("c.rs"), "pub trait Greet {\n    fn hello(&self);\n}\n").unwrap();
+	}
+
+	#[test]
+	fn parallel_rendering_is_byte_identical_across_runs() {
+		let dir = fixture_dir("determinism");
+		write_multi_file_fixture(&dir);
+
+		let first = run(&dir, false, false, false).unwrap();
+		let second = run(&dir, false, false, false).unwrap();
+		assert_eq!(first, second);
+		// ファイルの列挙順に関わらず、アルファベット順に並んでいること
+		assert!(first.find("a.rs").unwrap() < first.find("b.rs").unwrap());
+		assert!(first.find("b.rs").unwrap() < first.find("c.rs").unwrap());
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn format_json_emits_one_file_summary_per_file_with_tagged_items() {
+		let dir = fixture_dir("format-json");
+		write_multi_file_fixture(&dir);
+
+		let output = run(&dir, true, false, false).unwrap();
+		let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+		let files = parsed.as_array().unwrap();
+		assert_eq!(files.len(), 3);
+
+		let a = files.iter().find(|f| f["path"] == "a.rs").unwrap();
+		let items = a["items"].as_array().unwrap();
+		assert!(items
+			.iter()
+			.any(|item| item["kind"] == "Fn" && item["ident"] == "pub_fn"));
+		assert!(items
+			.iter()
+			.any(|item| item["kind"] == "Fn" && item["ident"] == "private_fn"));
+		assert!(items
+			.iter()
+			.any(|item| item["kind"] == "Struct" && item["ident"] == "Internal"));
+
+		let b = files.iter().find(|f| f["path"] == "b.rs").unwrap();
+		let inner_mod = b["items"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.find(|item| item["kind"] == "Mod" && item["ident"] == "inner")
+			.unwrap();
+		assert!(inner_mod["items"]
+			.as_array()
+			.unwrap()
+			.iter()
+			.any(|item| item["kind"] == "Fn" && item["ident"] == "inner_pub"));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn index_respects_public_filter() {
+		let dir = fixture_dir("index-public");
+		write_multi_file_fixture(&dir);
+
+		let full_index = run(&dir, false, true, false).unwrap();
+		assert!(full_index.contains("private_fn"));
+		assert!(full_index.contains("Internal"));
+		assert!(full_index.contains("inner_private"));
+
+		let public_index = run(&dir, false, true, true).unwrap();
+		assert!(public_index.contains("pub_fn"));
+		assert!(public_index.contains("inner_pub"));
+		assert!(public_index.contains("Greet"));
+		assert!(!public_index.contains("private_fn"));
+		assert!(!public_index.contains("Internal"));
+		assert!(!public_index.contains("inner_private"));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn public_flag_hides_non_public_items_in_rendered_output() {
+		let dir = fixture_dir("public-render");
+		write_multi_file_fixture(&dir);
+
+		let output = run(&dir, false, false, true).unwrap();
+		assert!(output.contains("pub fn pub_fn"));
+		assert!(!output.contains("private_fn"));
+		assert!(!output.contains("Internal"));
+		assert!(!output.contains("inner_private"));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	fn write_derive_cfg_fixture(dir: &Path) {
+		fs::write(
+			dir.join("attrs.rs"),
+			"#[derive(Debug, Clone)]\n#[cfg(feature = \"x\")]\npub struct Tagged;\n\npub trait Greet {\n    #[cfg(feature = \"y\")]\n    fn hello(&self);\n}\n\nimpl Tagged {\n    #[derive(Debug)]\n    #[cfg(feature = \"z\")]\n    pub fn method_with_attrs(&self) {}\n}\n",
+		)
+		.unwrap();
+	}
+
+	#[test]
+	fn derive_and_cfg_are_summarized_in_rendered_output() {
+		let dir = fixture_dir("derive-cfg-render");
+		write_derive_cfg_fixture(&dir);
+
+		let output = run(&dir, false, false, false).unwrap();
+		assert!(output.contains("// derive(Debug, Clone) cfg(feature = \"x\")"));
+		// トレイトメソッドの属性（chunk0-6 のフォローアップで直した経路）
+		assert!(output.contains("// cfg(feature = \"y\")"));
+		// impl メソッドの属性も同様に出ること
+		assert!(output.contains("// derive(Debug) cfg(feature = \"z\")"));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
+
+	#[test]
+	fn derive_and_cfg_are_summarized_in_json_output() {
+		let dir = fixture_dir("derive-cfg-json");
+		write_derive_cfg_fixture(&dir);
+
+		let output = run(&dir, true, false, false).unwrap();
+		let parsed: serde_json::Value = serde_json::from_str(&output).unwrap();
+		let items = parsed[0]["items"].as_array().unwrap();
+
+		let tagged = items
+			.iter()
+			.find(|item| item["kind"] == "Struct" && item["ident"] == "Tagged")
+			.unwrap();
+		assert_eq!(tagged["derive"], serde_json::json!(["Debug", "Clone"]));
+		assert_eq!(tagged["cfg"], serde_json::json!(["feature = \"x\""]));
+
+		let greet = items
+			.iter()
+			.find(|item| item["kind"] == "Trait" && item["ident"] == "Greet")
+			.unwrap();
+		let hello = greet["methods"].as_array().unwrap().first().unwrap();
+		assert_eq!(hello["cfg"], serde_json::json!(["feature = \"y\""]));
+
+		let tagged_impl = items
+			.iter()
+			.find(|item| item["kind"] == "Impl" && item["header"].as_str().unwrap().contains("Tagged"))
+			.unwrap();
+		let method = tagged_impl["methods"].as_array().unwrap().first().unwrap();
+		assert_eq!(method["derive"], serde_json::json!(["Debug"]));
+		assert_eq!(method["cfg"], serde_json::json!(["feature = \"z\""]));
+
+		let _ = fs::remove_dir_all(&dir);
+	}
 }